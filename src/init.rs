@@ -19,9 +19,10 @@ use crate::{
     raw::{
         tableGenBitInitGetValue, tableGenBitsInitGetBitInit, tableGenBitsInitGetNumBits,
         tableGenDagRecordArgName, tableGenDagRecordGet, tableGenDagRecordNumArgs,
-        tableGenDagRecordOperator, tableGenDefInitGetValue, tableGenInitPrint, tableGenInitRecType,
-        tableGenIntInitGetValue, tableGenListRecordGet, tableGenListRecordNumElements,
-        tableGenStringInitGetValue, TableGenRecTyKind, TableGenTypedInitRef,
+        tableGenDagRecordOperator, tableGenDefInitGetValue, tableGenInitFold, tableGenInitIsUnset,
+        tableGenInitPrint, tableGenInitRecType, tableGenIntInitGetValue, tableGenListRecordGet,
+        tableGenListRecordNumElements, tableGenStringInitGetValue, TableGenRecTyKind,
+        TableGenTypedInitRef,
     },
     string_ref::StringRef,
     util::print_callback,
@@ -48,6 +49,9 @@ pub enum TypedInit<'a> {
     List(ListInit<'a>),
     Dag(DagInit<'a>),
     Def(DefInit<'a>),
+    /// An uninitialized (`?`) field, distinct from [`TypedInit::Invalid`]
+    /// which means the underlying init kind wasn't recognized at all.
+    Unset,
     Invalid,
 }
 
@@ -62,6 +66,7 @@ impl<'a> TypedInit<'a> {
             TypedInit::List(_) => "List",
             TypedInit::Dag(_) => "Dag",
             TypedInit::Def(_) => "Def",
+            TypedInit::Unset => "Unset",
             TypedInit::Invalid => "Invalid",
         }
     }
@@ -78,6 +83,7 @@ impl<'a> Display for TypedInit<'a> {
             Self::List(init) => write!(f, "{}", &init),
             Self::Dag(init) => write!(f, "{}", &init),
             Self::Def(init) => write!(f, "{}", &init),
+            Self::Unset => write!(f, "?"),
             Self::Invalid => write!(f, "Invalid"),
         }
     }
@@ -97,6 +103,7 @@ impl<'a> Debug for TypedInit<'a> {
             Self::List(init) => write!(f, "{:#?}", &init),
             Self::Dag(init) => write!(f, "{:#?}", &init),
             Self::Def(init) => write!(f, "{:#?}", &init),
+            Self::Unset => write!(f, ""),
             Self::Invalid => write!(f, ""),
         }?;
         write!(f, "))")
@@ -181,6 +188,58 @@ impl<'a> TryFrom<TypedInit<'a>> for &'a str {
     }
 }
 
+/// Converts an unset (`?`) field to `None` rather than a conversion error,
+/// so optional record fields can be extracted with
+/// `let x: Option<i64> = init.try_into()?;` instead of special-casing
+/// [`TypedInit::Unset`] by hand.
+impl<'a, T> TryFrom<TypedInit<'a>> for Option<T>
+where
+    T: TryFrom<TypedInit<'a>, Error = Error>,
+{
+    type Error = Error;
+
+    fn try_from(value: TypedInit<'a>) -> Result<Self, Self::Error> {
+        match value {
+            TypedInit::Unset => Ok(None),
+            value => T::try_from(value).map(Some),
+        }
+    }
+}
+
+/// Converts a [`TypedInit`] into a Rust type, returning a descriptive
+/// [`TableGenError::InitConversion`] instead of requiring callers to match on
+/// each variant themselves.
+///
+/// Used by [`Record::value_as`](crate::record::Record::value_as) to turn a
+/// def's fields into a Rust struct. Blanket-implemented for [`Vec<T>`] so
+/// list fields compose the same way as scalar ones.
+pub trait FromInit<'a>: Sized {
+    fn from_init(init: TypedInit<'a>) -> Result<Self, Error>;
+}
+
+macro_rules! from_init_via_try_from {
+    ($type:ty) => {
+        impl<'a> FromInit<'a> for $type {
+            fn from_init(init: TypedInit<'a>) -> Result<Self, Error> {
+                Self::try_from(init)
+            }
+        }
+    };
+}
+
+from_init_via_try_from!(bool);
+from_init_via_try_from!(i64);
+from_init_via_try_from!(String);
+from_init_via_try_from!(&'a str);
+from_init_via_try_from!(Record<'a>);
+
+impl<'a, T: FromInit<'a>> FromInit<'a> for Vec<T> {
+    fn from_init(init: TypedInit<'a>) -> Result<Self, Error> {
+        let list = ListInit::try_from(init)?;
+        list.iter().map(T::from_init).collect()
+    }
+}
+
 impl<'a> TypedInit<'a> {
     as_inner!(bit, Bit, BitInit);
     as_inner!(bits, Bits, BitsInit);
@@ -198,6 +257,10 @@ impl<'a> TypedInit<'a> {
     /// The raw object must be valid.
     #[allow(non_upper_case_globals)]
     pub unsafe fn from_raw(init: TableGenTypedInitRef) -> Self {
+        if tableGenInitIsUnset(init) > 0 {
+            return Self::Unset;
+        }
+
         let t = tableGenInitRecType(init);
 
         use TableGenRecTyKind::*;
@@ -212,6 +275,41 @@ impl<'a> TypedInit<'a> {
             _ => Self::Invalid,
         }
     }
+
+    /// Folds unresolved `!`-operator expressions (`!add`, `!mul`, `!sub`,
+    /// `!strconcat`, `!eq`/`!ne`/`!lt`, `!if`, `!size`/`!empty`, ...) to a
+    /// concrete value, leaving the init unchanged if any operand is still
+    /// symbolic.
+    ///
+    /// [`TypedInit::Unset`] and [`TypedInit::Invalid`] carry no init to fold
+    /// and are returned unchanged.
+    ///
+    /// An unresolved operator init is an LLVM `OpInit` subclass with its own
+    /// operand tree that this crate doesn't otherwise expose
+    /// ([`TypedInit::from_raw`] classifies it by its value type only, e.g.
+    /// an `!add` as [`TypedInit::Int`]), so rather than re-implementing
+    /// TableGen's operator semantics
+    /// in Rust — and drifting from upstream every time a `!`-operator is
+    /// added — this re-runs the same constant-folding pass TableGen itself
+    /// applies when resolving a record.
+    pub fn evaluate(self) -> Self {
+        let raw = match self {
+            Self::Bit(v) => v.to_raw(),
+            Self::Bits(v) => v.to_raw(),
+            Self::Code(v) | Self::String(v) => v.to_raw(),
+            Self::Int(v) => v.to_raw(),
+            Self::List(v) => v.to_raw(),
+            Self::Dag(v) => v.to_raw(),
+            Self::Def(v) => v.to_raw(),
+            Self::Unset | Self::Invalid => return self,
+        };
+        let folded = unsafe { tableGenInitFold(raw) };
+        if folded.is_null() {
+            self
+        } else {
+            unsafe { Self::from_raw(folded) }
+        }
+    }
 }
 
 macro_rules! init {
@@ -234,6 +332,10 @@ macro_rules! init {
                     _reference: PhantomData,
                 }
             }
+
+            pub(crate) fn to_raw(self) -> TableGenTypedInitRef {
+                self.raw
+            }
         }
 
         impl<'a> Display for $name<'a> {
@@ -368,7 +470,9 @@ init!(DagInit);
 impl<'a> DagInit<'a> {
     /// Returns an iterator over the arguments of the dag.
     ///
-    /// The iterator yields tuples `(&str, TypedInit)`.
+    /// The iterator yields tuples `(Option<&str>, TypedInit)`, since dag
+    /// arguments (e.g. the `$dst`/`$src` tags in `(ops R:$dst, R:$src)`)
+    /// aren't required to carry a name.
     pub fn args(self) -> DagIter<'a> {
         DagIter {
             dag: self,
@@ -376,6 +480,13 @@ impl<'a> DagInit<'a> {
         }
     }
 
+    /// Returns an iterator over the argument values of the dag, discarding
+    /// their names. A convenience wrapper around [`DagInit::args`] for
+    /// callers that don't care about the `$name` tags.
+    pub fn values(self) -> impl Iterator<Item = TypedInit<'a>> {
+        self.args().map(|(_, value)| value)
+    }
+
     /// Returns the operator of the dag as a [`Record`].
     pub fn operator(self) -> Record<'a> {
         unsafe { Record::from_raw(tableGenDagRecordOperator(self.raw)) }
@@ -410,17 +521,13 @@ pub struct DagIter<'a> {
 }
 
 impl<'a> Iterator for DagIter<'a> {
-    type Item = (&'a str, TypedInit<'a>);
+    type Item = (Option<&'a str>, TypedInit<'a>);
 
     fn next(&mut self) -> Option<Self::Item> {
-        let next = self.dag.get(self.index);
+        let next = self.dag.get(self.index)?;
         let name = self.dag.name(self.index);
         self.index += 1;
-        if let (Some(next), Some(name)) = (next, name) {
-            Some((name, next))
-        } else {
-            None
-        }
+        Some((name, next))
     }
 }
 
@@ -478,6 +585,158 @@ impl<'a> Iterator for ListIter<'a> {
     }
 }
 
+/// Serializes a [`TypedInit`] the way `llvm-tblgen --dump-json` would: bits
+/// as an array of `0`/`1`, lists recursively, dags as `{operator, args}`
+/// and def references as a tagged `{kind: "def", def: <name>}` object.
+///
+/// Delegates to each variant's own [`serde::Serialize`] impl (e.g.
+/// [`BitsInit`], [`DagInit`]), so the shape is identical whether a value is
+/// serialized through the enum or through one of its wrapper types directly.
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for TypedInit<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::Bit(b) => b.serialize(serializer),
+            Self::Bits(b) => b.serialize(serializer),
+            Self::Int(i) => i.serialize(serializer),
+            Self::String(s) | Self::Code(s) => s.serialize(serializer),
+            Self::List(l) => l.serialize(serializer),
+            Self::Dag(d) => d.serialize(serializer),
+            Self::Def(d) => d.serialize(serializer),
+            Self::Unset => serializer.serialize_none(),
+            Self::Invalid => serializer.serialize_none(),
+        }
+    }
+}
+
+/// Serializes as a plain `bool`.
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for BitInit<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_bool((*self).into())
+    }
+}
+
+/// Serializes as an array of `0`/`1`, one per bit, matching
+/// `llvm-tblgen --dump-json`'s representation of a `bits` value (not a JSON
+/// array of booleans).
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for BitsInit<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeSeq;
+
+        let bits: Vec<bool> = (*self).into();
+        let mut seq = serializer.serialize_seq(Some(bits.len()))?;
+        for bit in bits {
+            seq.serialize_element(&(bit as u8))?;
+        }
+        seq.end()
+    }
+}
+
+/// Serializes as a plain `i64`.
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for IntInit<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_i64((*self).into())
+    }
+}
+
+/// Serializes as a plain string, shared by both the `String` and `Code`
+/// [`TypedInit`] variants.
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for StringInit<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::Error as _;
+
+        serializer.serialize_str(
+            self.to_str()
+                .map_err(TableGenError::from)
+                .map_err(S::Error::custom)?,
+        )
+    }
+}
+
+/// Serializes as a tagged `{kind: "def", def: <name>}` object, referencing
+/// the record by name rather than recursing into its fields.
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for DefInit<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::{Error as _, SerializeMap};
+
+        let record: Record = (*self).into();
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("kind", "def")?;
+        map.serialize_entry("def", record.name().map_err(S::Error::custom)?)?;
+        map.end()
+    }
+}
+
+/// A single dag argument, serialized as `{name, value}` rather than a bare
+/// tuple so the field names are self-describing in the output.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct DagArg<'a> {
+    name: Option<&'a str>,
+    value: TypedInit<'a>,
+}
+
+/// Serializes as `{operator, args}`, where `operator` is the dag's operator
+/// record name and `args` is an ordered list of `{name, value}` entries.
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for DagInit<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::{Error as _, SerializeMap};
+
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("operator", self.operator().name().map_err(S::Error::custom)?)?;
+        let args: Vec<DagArg> = self
+            .args()
+            .map(|(name, value)| DagArg { name, value })
+            .collect();
+        map.serialize_entry("args", &args)?;
+        map.end()
+    }
+}
+
+/// Serializes as a sequence of the list's elements.
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for ListInit<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeSeq;
+
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for item in self.iter() {
+            seq.serialize_element(&item)?;
+        }
+        seq.end()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -518,6 +777,25 @@ mod tests {
     test_init!(int, "int a = 42;", 42);
     test_init!(string, "string a = \"hi\";", "hi");
 
+    #[cfg(feature = "json")]
+    #[test]
+    fn bits_serializes_as_0_1_array_not_booleans() {
+        let rk = TableGenParser::new()
+            .add_source(
+                "
+                def A {
+                    bits<4> a = { 0, 0, 1, 0 };
+                }
+                ",
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = rk.def("A").expect("def A exists").value("a").expect("field a exists");
+        let json = serde_json::to_value(&a.init).expect("serializes");
+        assert_eq!(json, serde_json::json!([0, 1, 0, 0]));
+    }
+
     #[test]
     fn dag() {
         let rk = TableGenParser::new()
@@ -553,15 +831,16 @@ mod tests {
                 name,
                 Record::try_from(init).expect("is record").int_value("i")
             )),
-            Some(("src1", Ok(4)))
+            Some((Some("src1"), Ok(4)))
         );
         assert_eq!(
             args.nth(1).map(|(name, init)| (
                 name,
                 Record::try_from(init).expect("is record").string_value("s")
             )),
-            Some(("src2", Ok("test".into())))
+            Some((Some("src2"), Ok("test".into())))
         );
+        assert_eq!(a.values().count(), 2);
     }
 
     #[test]
@@ -21,12 +21,14 @@
 //!
 //! # Supported LLVM Versions
 //!
-//! An installation of LLVM is required to use this crate.
-//! This crate only aims to support the latest version of LLVM. The version of
-//! LLVM currently supported is 17.x.x.
+//! An installation of LLVM is required to use this crate. LLVM 16.x, 17.x and
+//! 18.x are supported, selected through the `llvm16-0`, `llvm17-0` and
+//! `llvm18-0` Cargo features respectively (`llvm17-0` is the default). See
+//! [`SUPPORTED_LLVM_VERSION`] to read back which one is active.
 //!
-//! The `TABLEGEN_170_PREFIX` environment variable can be used to specify a
-//! custom directory of the LLVM installation.
+//! Each feature looks up its own prefix environment variable to specify a
+//! custom directory of the LLVM installation: `TABLEGEN_160_PREFIX`,
+//! `TABLEGEN_170_PREFIX` or `TABLEGEN_180_PREFIX`.
 //!
 //! # Examples
 //!
@@ -84,6 +86,10 @@ pub mod init;
 pub mod record;
 /// TableGen record keeper.
 pub mod record_keeper;
+/// Owned, keeper-independent snapshots of records and their values.
+pub mod owned;
+/// Opt-in schema validation for [`record_keeper::RecordKeeper`]s.
+pub mod schema;
 mod string_ref;
 mod util;
 
@@ -98,9 +104,53 @@ pub mod raw {
 
 use std::ffi::CStr;
 use std::ffi::CString;
+use std::fmt;
 use std::marker::PhantomData;
 use std::sync::Mutex;
 
+/// An LLVM major version supported by this crate's `llvmNN-0` Cargo
+/// features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LlvmVersion {
+    V16,
+    V17,
+    V18,
+}
+
+impl LlvmVersion {
+    /// The name of the environment variable used to override the LLVM
+    /// installation prefix for this version, e.g. `TABLEGEN_170_PREFIX`.
+    pub fn prefix_env_var(self) -> &'static str {
+        match self {
+            Self::V16 => "TABLEGEN_160_PREFIX",
+            Self::V17 => "TABLEGEN_170_PREFIX",
+            Self::V18 => "TABLEGEN_180_PREFIX",
+        }
+    }
+}
+
+impl fmt::Display for LlvmVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::V16 => write!(f, "16"),
+            Self::V17 => write!(f, "17"),
+            Self::V18 => write!(f, "18"),
+        }
+    }
+}
+
+/// The LLVM major version this crate was built against, selected via the
+/// `llvm16-0`/`llvm17-0`/`llvm18-0` Cargo features.
+#[cfg(feature = "llvm16-0")]
+pub const SUPPORTED_LLVM_VERSION: LlvmVersion = LlvmVersion::V16;
+#[cfg(feature = "llvm17-0")]
+pub const SUPPORTED_LLVM_VERSION: LlvmVersion = LlvmVersion::V17;
+#[cfg(feature = "llvm18-0")]
+pub const SUPPORTED_LLVM_VERSION: LlvmVersion = LlvmVersion::V18;
+
+use error::Diagnostic;
+use error::Diagnostics;
 use error::InvalidSourceError;
 use error::ParseError;
 pub use init::TypedInit;
@@ -121,13 +171,22 @@ static TABLEGEN_PARSE_LOCK: Mutex<()> = Mutex::new(());
 
 /// Builder struct that parses TableGen source files and builds a
 /// [`RecordKeeper`].
-#[derive(Debug, PartialEq, Eq)]
 pub struct TableGenParser<'s> {
     raw: TableGenParserRef,
     source_strings: Vec<CString>,
+    include_resolver: Option<Box<util::IncludeResolverState>>,
     _source_ref: PhantomData<&'s str>,
 }
 
+impl<'s> std::fmt::Debug for TableGenParser<'s> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("TableGenParser")
+            .field("raw", &self.raw)
+            .field("source_strings", &self.source_strings)
+            .finish_non_exhaustive()
+    }
+}
+
 impl<'s> Default for TableGenParser<'s> {
     fn default() -> Self {
         Self::new()
@@ -140,6 +199,7 @@ impl<'s> TableGenParser<'s> {
         Self {
             raw: unsafe { tableGenGet() },
             source_strings: Vec::new(),
+            include_resolver: None,
             _source_ref: PhantomData,
         }
     }
@@ -150,6 +210,33 @@ impl<'s> TableGenParser<'s> {
         self
     }
 
+    /// Registers a closure that is consulted whenever TableGen opens the file
+    /// named by an `include "..."` directive, before the directories
+    /// registered with [`TableGenParser::add_include_path`] are searched, so
+    /// sources can be served from memory (e.g. via `include_str!` in a build
+    /// script) instead of disk.
+    ///
+    /// Returning `None` from the closure falls through to TableGen's normal
+    /// filesystem lookup.
+    pub fn add_include_resolver(
+        mut self,
+        resolver: impl FnMut(&str) -> Option<String> + 'static,
+    ) -> Self {
+        let mut state = Box::new(util::IncludeResolverState {
+            resolver: Box::new(resolver),
+            resolved: Vec::new(),
+        });
+        unsafe {
+            raw::tableGenSetIncludeResolver(
+                self.raw,
+                Some(util::include_resolver_callback),
+                state.as_mut() as *mut util::IncludeResolverState as *mut std::ffi::c_void,
+            );
+        }
+        self.include_resolver = Some(state);
+        self
+    }
+
     /// Reads TableGen source code from the file at the given path.
     pub fn add_source_file(self, source: &str) -> Result<Self, InvalidSourceError> {
         if unsafe { tableGenAddSourceFile(self.raw, StringRef::from(source).to_raw()) > 0 } {
@@ -194,14 +281,62 @@ impl<'s> TableGenParser<'s> {
     /// Due to limitations of TableGen, parsing TableGen is not thread-safe.
     /// In order to provide thread-safety, this method ensures that any
     /// concurrent parse operations are executed sequentially.
+    ///
+    /// On failure, the returned [`ParseError`] carries every diagnostic
+    /// TableGen printed while parsing (severity, file, line, column, message
+    /// and the offending source snippet), rather than a bare failure.
     pub fn parse(self) -> Result<RecordKeeper<'s>, ParseError> {
         unsafe {
             let guard = TABLEGEN_PARSE_LOCK.lock().unwrap();
+            let mut diagnostics: Vec<Diagnostic> = Vec::new();
+            raw::tableGenSourceMgrSetDiagHandler(
+                self.raw,
+                Some(util::diagnostic_callback),
+                &mut diagnostics as *mut _ as *mut std::ffi::c_void,
+            );
             let keeper = tableGenParse(self.raw);
+            // Tear down the handler now: it points at `diagnostics`, which is
+            // popped off this stack frame the instant we return, and
+            // `SourceMgr::PrintMessage` (invoked by `SourceError::add_source_info`
+            // et al.) routes to it whenever one is installed.
+            raw::tableGenSourceMgrSetDiagHandler(self.raw, None, std::ptr::null_mut());
             let res = if !keeper.is_null() {
                 Ok(RecordKeeper::from_raw(keeper, self))
             } else {
-                Err(ParseError)
+                Err(ParseError { diagnostics })
+            };
+            drop(guard);
+            res
+        }
+    }
+
+    /// Like [`TableGenParser::parse`], but returns every diagnostic TableGen
+    /// printed while parsing as a [`Diagnostics`] container instead of
+    /// bailing at the first error.
+    ///
+    /// On success the [`RecordKeeper`] is returned alongside the
+    /// diagnostics collected so far, so warnings aren't silently discarded
+    /// when parsing otherwise succeeds. On failure only the diagnostics are
+    /// returned; check [`Diagnostics::errors`] for the full set of problems
+    /// instead of recompiling to find them one at a time.
+    pub fn parse_collecting(self) -> Result<(RecordKeeper<'s>, Diagnostics), Diagnostics> {
+        unsafe {
+            let guard = TABLEGEN_PARSE_LOCK.lock().unwrap();
+            let mut diagnostics: Vec<Diagnostic> = Vec::new();
+            raw::tableGenSourceMgrSetDiagHandler(
+                self.raw,
+                Some(util::diagnostic_callback),
+                &mut diagnostics as *mut _ as *mut std::ffi::c_void,
+            );
+            let keeper = tableGenParse(self.raw);
+            // See the matching teardown in `parse` above: the handler must
+            // not outlive `diagnostics`, which goes out of scope on return.
+            raw::tableGenSourceMgrSetDiagHandler(self.raw, None, std::ptr::null_mut());
+            let diagnostics = Diagnostics::new(diagnostics);
+            let res = if !keeper.is_null() {
+                Ok((RecordKeeper::from_raw(keeper, self), diagnostics))
+            } else {
+                Err(diagnostics)
             };
             drop(guard);
             res
@@ -216,3 +351,42 @@ impl<'s> Drop for TableGenParser<'s> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use error::DiagKind;
+
+    #[test]
+    fn parse_failure_collects_diagnostics() {
+        let err = TableGenParser::new()
+            .add_source("def A { not_a_type field; }")
+            .unwrap()
+            .parse()
+            .expect_err("malformed source must fail to parse");
+        assert!(!err.diagnostics.is_empty());
+        assert!(err.diagnostics.iter().any(|d| d.kind == DiagKind::Error));
+    }
+
+    #[test]
+    fn parse_collecting_succeeds_with_empty_diagnostics() {
+        let (_keeper, diagnostics) = TableGenParser::new()
+            .add_source("def A;")
+            .unwrap()
+            .parse_collecting()
+            .expect("valid tablegen");
+        assert!(!diagnostics.has_errors());
+        assert_eq!(diagnostics.errors().count(), 0);
+    }
+
+    #[test]
+    fn parse_collecting_failure_reports_errors() {
+        let diagnostics = TableGenParser::new()
+            .add_source("def A { not_a_type field; }")
+            .unwrap()
+            .parse_collecting()
+            .expect_err("malformed source must fail to parse");
+        assert!(diagnostics.has_errors());
+        assert!(diagnostics.errors().count() > 0);
+    }
+}
@@ -0,0 +1,235 @@
+// Original work Copyright 2016 Alexander Stocko <as@coder.gg>.
+// Modified work Copyright 2023 Daan Vanoverloop
+// See the COPYRIGHT file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Owned, keeper-independent mirrors of [`Record`] and its values.
+//!
+//! Every [`Record`]/[`RecordValue`](crate::record::RecordValue)/[`TypedInit`]
+//! borrows the [`RecordKeeper`] it came from, so it cannot outlive the parser
+//! or be sent to another thread. [`OwnedRecord`], [`OwnedValue`] and
+//! [`OwnedKeeper`] deep-copy that data so it can be stored, cached, or shipped
+//! across threads. With the `serde` feature enabled, [`OwnedKeeper`] can be
+//! round-tripped through CBOR, letting a program parse `.td` files once and
+//! load the resolved record set directly on later runs.
+//!
+//! [`TypedInit::to_owned`] converts a single borrowed subtree in one
+//! recursive pass without going through a [`Record`] at all, for callers
+//! that only have a loose [`TypedInit`] (e.g. a list element or dag
+//! argument) rather than a whole record.
+
+use std::collections::BTreeMap;
+
+use crate::init::TypedInit;
+use crate::record::Record;
+use crate::record_keeper::RecordKeeper;
+
+/// An owned mirror of [`TypedInit`] that does not borrow a [`RecordKeeper`].
+///
+/// `Def` stores the referenced record's name rather than the record itself;
+/// re-link it by looking the name up in the [`OwnedKeeper`] it came from.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OwnedValue {
+    Bit(bool),
+    Bits(Vec<bool>),
+    Int(i64),
+    String(String),
+    Code(String),
+    List(Vec<OwnedValue>),
+    Dag {
+        operator: String,
+        args: Vec<(Option<String>, OwnedValue)>,
+    },
+    Def(String),
+    /// Mirrors an uninitialized (`?`) field.
+    Unset,
+    Invalid,
+}
+
+/// An owned mirror of [`Record`]: its name and every field, deep-copied.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedRecord {
+    pub name: String,
+    pub values: BTreeMap<String, OwnedValue>,
+}
+
+/// An owned, deep-copied snapshot of every def in a [`RecordKeeper`].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedKeeper {
+    pub records: BTreeMap<String, OwnedRecord>,
+}
+
+impl<'a> Record<'a> {
+    /// Deep-copies this record into an owned, keeper-independent
+    /// [`OwnedRecord`] by walking [`Record::values`].
+    pub fn to_owned(self) -> OwnedRecord {
+        OwnedRecord {
+            name: self.name().unwrap_or_default().to_string(),
+            values: self
+                .values()
+                .filter_map(|value| {
+                    value
+                        .name
+                        .to_str()
+                        .ok()
+                        .map(|name| (name.to_string(), owned_value(value.init)))
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<'a> TypedInit<'a> {
+    /// Deep-copies this value into an owned, keeper-independent
+    /// [`OwnedValue`], recursively converting nested lists and dag arguments
+    /// in one pass instead of requiring callers to walk
+    /// [`ListIter`](crate::init::ListIter)/[`DagIter`](crate::init::DagIter)
+    /// by hand.
+    pub fn to_owned(self) -> OwnedValue {
+        owned_value(self)
+    }
+}
+
+fn owned_value(init: TypedInit) -> OwnedValue {
+    match init {
+        TypedInit::Bit(b) => OwnedValue::Bit(b.into()),
+        TypedInit::Bits(b) => OwnedValue::Bits(b.into()),
+        TypedInit::Int(i) => OwnedValue::Int(i.into()),
+        TypedInit::String(s) => OwnedValue::String(s.to_str().unwrap_or_default().to_string()),
+        TypedInit::Code(s) => OwnedValue::Code(s.to_str().unwrap_or_default().to_string()),
+        TypedInit::List(l) => OwnedValue::List(l.iter().map(owned_value).collect()),
+        TypedInit::Dag(d) => OwnedValue::Dag {
+            operator: d.operator().name().unwrap_or_default().to_string(),
+            args: d
+                .args()
+                .map(|(name, init)| (name.map(str::to_string), owned_value(init)))
+                .collect(),
+        },
+        TypedInit::Def(d) => {
+            let record: Record = d.into();
+            OwnedValue::Def(record.name().unwrap_or_default().to_string())
+        }
+        TypedInit::Unset => OwnedValue::Unset,
+        TypedInit::Invalid => OwnedValue::Invalid,
+    }
+}
+
+impl OwnedKeeper {
+    /// Deep-copies every def in `keeper` into an [`OwnedKeeper`].
+    pub fn from_record_keeper(keeper: &RecordKeeper) -> Self {
+        Self {
+            records: keeper
+                .defs()
+                .filter_map(|(name, def)| name.ok().map(|name| (name.to_string(), def.to_owned())))
+                .collect(),
+        }
+    }
+
+    /// Looks up a def by name, e.g. to re-link an [`OwnedValue::Def`]
+    /// reference to the record it names.
+    pub fn get(&self, name: &str) -> Option<&OwnedRecord> {
+        self.records.get(name)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod cbor {
+    use super::OwnedKeeper;
+    use std::io::{Read, Write};
+
+    impl OwnedKeeper {
+        /// Encodes this keeper as CBOR.
+        pub fn to_cbor(&self) -> Result<Vec<u8>, ciborium::ser::Error<std::io::Error>> {
+            let mut buf = Vec::new();
+            ciborium::ser::into_writer(self, &mut buf)?;
+            Ok(buf)
+        }
+
+        /// Decodes a keeper previously written by [`OwnedKeeper::to_cbor`].
+        pub fn from_cbor(reader: impl Read) -> Result<Self, ciborium::de::Error<std::io::Error>> {
+            ciborium::de::from_reader(reader)
+        }
+
+        /// Writes this keeper as CBOR to a file, to be loaded on a later run
+        /// via [`OwnedKeeper::from_cbor`] instead of re-invoking the parser.
+        pub fn write_cbor(&self, writer: impl Write) -> Result<(), ciborium::ser::Error<std::io::Error>> {
+            ciborium::ser::into_writer(self, writer)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod cbor_tests {
+    use super::*;
+    use crate::TableGenParser;
+
+    #[test]
+    fn cbor_round_trip() {
+        let rk = TableGenParser::new()
+            .add_source(
+                "
+                def A {
+                    int size = 42;
+                    string name = \"hello\";
+                }
+                ",
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let keeper = OwnedKeeper::from_record_keeper(&rk);
+
+        let bytes = keeper.to_cbor().expect("encodes to cbor");
+        let decoded = OwnedKeeper::from_cbor(bytes.as_slice()).expect("decodes from cbor");
+
+        assert_eq!(keeper, decoded);
+        let a = decoded.get("A").expect("A round-tripped");
+        assert_eq!(a.values.get("size"), Some(&OwnedValue::Int(42)));
+        assert_eq!(
+            a.values.get("name"),
+            Some(&OwnedValue::String("hello".to_string()))
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TableGenParser;
+
+    #[test]
+    fn typed_init_to_owned() {
+        let rk = TableGenParser::new()
+            .add_source(
+                "
+                def A {
+                    list<int> l = [1, 2, 3];
+                    int unset_field = ?;
+                }
+                ",
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = rk.def("A").expect("def A exists");
+        let l = a.value("l").expect("field l exists");
+        assert_eq!(
+            l.init.to_owned(),
+            OwnedValue::List(vec![
+                OwnedValue::Int(1),
+                OwnedValue::Int(2),
+                OwnedValue::Int(3),
+            ])
+        );
+        let unset = a.value("unset_field").expect("field unset_field exists");
+        assert_eq!(unset.init.to_owned(), OwnedValue::Unset);
+    }
+}
@@ -174,6 +174,18 @@ impl<'a> Record<'a> {
         }
     }
 
+    /// Returns the field with the given name converted to any type
+    /// implementing [`FromInit`](crate::init::FromInit), so backend authors
+    /// can deserialize a whole def's fields into a Rust struct without
+    /// manually matching on each [`TypedInit`] variant.
+    pub fn value_as<'n, T: crate::init::FromInit<'a>>(
+        self,
+        name: &'n str,
+    ) -> Result<T, SourceError<TableGenError>> {
+        let value = self.value(name)?;
+        T::from_init(value.init)
+    }
+
     /// Returns true if the record is anonymous.
     pub fn anonymous(self) -> bool {
         unsafe { tableGenRecordIsAnonymous(self.raw) > 0 }
@@ -311,6 +323,47 @@ impl<'a> Iterator for RecordValueIter<'a> {
     }
 }
 
+/// Serializes a [`Record`] as `llvm-tblgen --dump-json` would render a def:
+/// a map of field name to value, plus the `!name` and `!superclasses` tags.
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for Record<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::{Error as _, SerializeMap};
+
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("!name", self.name().map_err(S::Error::custom)?)?;
+        let superclasses: Vec<&str> = self
+            .keeper
+            .classes()
+            .filter_map(|(name, _)| name.ok())
+            .filter(|name| self.subclass_of(name))
+            .collect();
+        map.serialize_entry("!superclasses", &superclasses)?;
+        for value in self.values() {
+            map.serialize_entry(
+                value.name.to_str().map_err(TableGenError::from).map_err(S::Error::custom)?,
+                &value.init,
+            )?;
+        }
+        map.end()
+    }
+}
+
+/// Serializes a [`RecordValue`] as its resolved [`TypedInit`], dropping the
+/// field name since that is normally already the surrounding map's key.
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for RecordValue<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.init.serialize(serializer)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -348,6 +401,28 @@ mod tests {
         assert!(anon.subclass_of("C"));
     }
 
+    #[cfg(feature = "json")]
+    #[test]
+    fn serializes_like_llvm_tblgen_dump_json() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class Base;
+                def D : Base {
+                    int size = 4;
+                }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let d = rk.def("D").expect("def D exists");
+        let json = serde_json::to_value(&d).expect("serializes");
+        assert_eq!(json["!name"], "D");
+        assert_eq!(json["!superclasses"], serde_json::json!(["Base"]));
+        assert_eq!(json["size"], 4);
+    }
+
     #[test]
     fn single_value() {
         let rk = TableGenParser::new()
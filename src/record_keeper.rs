@@ -86,6 +86,17 @@ impl RecordKeeper {
             ))
         }
     }
+
+    /// Produces the same structured dump `llvm-tblgen --dump-json` emits: a
+    /// `!instanceof` reverse index from class name to the defs that derive
+    /// from it, plus one object per def with its resolved field values and
+    /// superclass list. Built on top of this keeper's [`serde::Serialize`]
+    /// implementation, which can also be used directly to emit the same
+    /// structure in any other serde-compatible format.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> serde_json::Result<serde_json::Value> {
+        serde_json::to_value(self)
+    }
 }
 
 impl Drop for RecordKeeper {
@@ -201,6 +212,41 @@ impl<'a> Drop for RecordIter<'a> {
     }
 }
 
+/// Serializes the whole [`RecordKeeper`] the way `llvm-tblgen --dump-json`
+/// does: a `!instanceof` index from class name to the defs that derive from
+/// it, followed by one entry per def keyed by its name.
+#[cfg(feature = "serde")]
+impl serde::Serialize for RecordKeeper {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::{Error as _, SerializeMap};
+        use std::collections::BTreeMap;
+
+        let mut map = serializer.serialize_map(None)?;
+
+        let mut instanceof: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (class_name, _) in self.classes() {
+            let class_name = class_name.map_err(S::Error::custom)?;
+            let derived: Vec<&str> = self
+                .defs()
+                .filter_map(|(name, def)| name.ok().map(|name| (name, def)))
+                .filter(|(_, def)| def.subclass_of(class_name))
+                .map(|(name, _)| name)
+                .collect();
+            instanceof.insert(class_name, derived);
+        }
+        map.serialize_entry("!instanceof", &instanceof)?;
+
+        for (name, def) in self.defs() {
+            map.serialize_entry(name.map_err(S::Error::custom)?, &def)?;
+        }
+
+        map.end()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::TableGenParser;
@@ -229,6 +275,31 @@ mod test {
         assert!(rk.defs().map(|i| i.0.unwrap()).eq(["D1", "D2", "D3"]));
     }
 
+    #[cfg(feature = "json")]
+    #[test]
+    fn to_json_matches_llvm_tblgen_dump_json_shape() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class A;
+                class B;
+                def D1: A;
+                def D2: A, B;
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let json = rk.to_json().expect("serializes");
+        assert_eq!(
+            json["!instanceof"]["A"],
+            serde_json::json!(["D1", "D2"])
+        );
+        assert_eq!(json["!instanceof"]["B"], serde_json::json!(["D2"]));
+        assert_eq!(json["D1"]["!name"], "D1");
+        assert_eq!(json["D2"]["!superclasses"], serde_json::json!(["A", "B"]));
+    }
+
     #[test]
     fn derived_defs() {
         let rk = TableGenParser::new()
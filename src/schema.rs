@@ -0,0 +1,212 @@
+// Original work Copyright 2016 Alexander Stocko <as@coder.gg>.
+// Modified work Copyright 2023 Daan Vanoverloop
+// See the COPYRIGHT file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Opt-in schema validation for [`RecordKeeper`]s.
+//!
+//! A [`Schema`] declares the fields a class of defs is expected to carry, and
+//! [`validate`] walks every matching def and checks them against it. Unlike
+//! the typed accessors on [`Record`], which return on the first conversion
+//! failure, [`validate`] accumulates every violation (with its
+//! [`SourceLocation`](crate::error::SourceLocation)) so a single run reports
+//! every mismatch in the file.
+//!
+//! ```ignore
+//! let schema = Schema::class("Instruction")
+//!     .field("size", FieldTy::Int)
+//!     .field("ops", FieldTy::List(Box::new(FieldTy::Def("Operand"))));
+//! let errors = validate(&keeper, &schema);
+//! ```
+
+use crate::error::{SourceError, TableGenError, WithLocation};
+use crate::init::TypedInit;
+use crate::record::Record;
+use crate::record_keeper::RecordKeeper;
+
+/// The expected type of a declared [`Schema`] field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldTy {
+    Bit,
+    Bits,
+    Int,
+    String,
+    Code,
+    List(Box<FieldTy>),
+    Dag,
+    /// A [`Record`] reference that must be a subclass of the named class.
+    Def(&'static str),
+}
+
+impl FieldTy {
+    fn description(&self) -> &'static str {
+        match self {
+            Self::Bit => "Bit",
+            Self::Bits => "Bits",
+            Self::Int => "Int",
+            Self::String => "String",
+            Self::Code => "Code",
+            Self::List(_) => "List",
+            Self::Dag => "Dag",
+            Self::Def(_) => "Def",
+        }
+    }
+}
+
+struct Field {
+    name: &'static str,
+    ty: FieldTy,
+}
+
+/// The expected shape of every def that is a subclass of [`Schema::class`].
+pub struct Schema {
+    class: &'static str,
+    fields: Vec<Field>,
+}
+
+impl Schema {
+    /// Declares the class whose subclasses this schema validates.
+    pub fn class(class: &'static str) -> Self {
+        Self {
+            class,
+            fields: Vec::new(),
+        }
+    }
+
+    /// Declares a required field and its expected type.
+    pub fn field(mut self, name: &'static str, ty: FieldTy) -> Self {
+        self.fields.push(Field { name, ty });
+        self
+    }
+}
+
+/// Validates every def in `keeper` that is a subclass of `schema`'s class,
+/// returning every violation found rather than stopping at the first.
+///
+/// At most one violation is reported per field: for a list field, that is
+/// its first element of the wrong type.
+pub fn validate<'a>(
+    keeper: &'a RecordKeeper,
+    schema: &Schema,
+) -> Vec<SourceError<TableGenError>> {
+    let mut errors = Vec::new();
+    for (_, def) in keeper.defs() {
+        if def.subclass_of(schema.class) {
+            validate_record(def, schema, &mut errors);
+        }
+    }
+    errors
+}
+
+fn validate_record<'a>(record: Record<'a>, schema: &Schema, errors: &mut Vec<SourceError<TableGenError>>) {
+    for field in &schema.fields {
+        match record.value(field.name) {
+            Err(e) => errors.push(e),
+            Ok(value) => {
+                if let Err(e) = check_ty(value.init, &field.ty) {
+                    errors.push(e.with_location(value));
+                }
+            }
+        }
+    }
+}
+
+fn check_ty<'a>(init: TypedInit<'a>, ty: &FieldTy) -> Result<(), TableGenError> {
+    match (ty, init) {
+        (FieldTy::Bit, TypedInit::Bit(_)) => Ok(()),
+        (FieldTy::Bits, TypedInit::Bits(_)) => Ok(()),
+        (FieldTy::Int, TypedInit::Int(_)) => Ok(()),
+        (FieldTy::String, TypedInit::String(_)) => Ok(()),
+        (FieldTy::Code, TypedInit::Code(_)) => Ok(()),
+        (FieldTy::Dag, TypedInit::Dag(_)) => Ok(()),
+        (FieldTy::Def(class), TypedInit::Def(d)) => {
+            let record: Record = d.into();
+            if record.subclass_of(class) {
+                Ok(())
+            } else {
+                Err(TableGenError::MissingClass((*class).to_string()))
+            }
+        }
+        (FieldTy::List(inner), TypedInit::List(list)) => {
+            list.iter().try_for_each(|item| check_ty(item, inner))
+        }
+        (ty, init) => Err(TableGenError::InitConversion {
+            from: init_description(&init),
+            to: ty.description(),
+        }),
+    }
+}
+
+fn init_description(init: &TypedInit) -> &'static str {
+    match init {
+        TypedInit::Bit(_) => "Bit",
+        TypedInit::Bits(_) => "Bits",
+        TypedInit::Code(_) => "Code",
+        TypedInit::Int(_) => "Int",
+        TypedInit::String(_) => "String",
+        TypedInit::List(_) => "List",
+        TypedInit::Dag(_) => "Dag",
+        TypedInit::Def(_) => "Def",
+        TypedInit::Unset => "Unset",
+        TypedInit::Invalid => "Invalid",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TableGenParser;
+
+    fn keeper() -> crate::RecordKeeper {
+        TableGenParser::new()
+            .add_source(
+                "
+                class Instruction {
+                    int size;
+                    string name;
+                }
+                def Add : Instruction {
+                    int size = 4;
+                    string name = \"add\";
+                }
+                def Bad : Instruction {
+                    int size = 4;
+                    string name = \"bad\";
+                }
+                ",
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen")
+    }
+
+    fn instruction_schema() -> Schema {
+        Schema::class("Instruction")
+            .field("size", FieldTy::Int)
+            .field("name", FieldTy::String)
+    }
+
+    #[test]
+    fn passing_schema_has_no_violations() {
+        let rk = keeper();
+        assert!(validate(&rk, &instruction_schema()).is_empty());
+    }
+
+    #[test]
+    fn violating_schema_reports_every_mismatch() {
+        let rk = keeper();
+        let schema = Schema::class("Instruction")
+            .field("size", FieldTy::String)
+            .field("opcode", FieldTy::Int);
+        let errors = validate(&rk, &schema);
+        // Two defs derive from Instruction, each missing `opcode` and
+        // mistyping `size`, so every violation is reported rather than
+        // stopping at the first.
+        assert_eq!(errors.len(), 4);
+    }
+}
@@ -3,7 +3,13 @@ use std::{
     fmt::{self, Formatter},
 };
 
-use crate::{error::TableGenError, raw::TableGenStringRef, string_ref::StringRef};
+use std::ffi::CString;
+
+use crate::{
+    error::{DiagKind, Diagnostic, TableGenError},
+    raw::TableGenStringRef,
+    string_ref::StringRef,
+};
 
 pub(crate) unsafe extern "C" fn print_callback(string: TableGenStringRef, data: *mut c_void) {
     let (formatter, result) = &mut *(data as *mut (&mut Formatter, fmt::Result));
@@ -37,3 +43,76 @@ pub(crate) unsafe extern "C" fn print_string_callback(
         Ok(())
     })();
 }
+
+/// Installed on LLVM's `SourceMgr` as the diagnostic handler for the
+/// duration of a parse; appends every emitted `SMDiagnostic` to the
+/// `Vec<Diagnostic>` behind `data`, passed through the user-data pointer the
+/// same way [`print_string_callback`] receives its `(writer, result)` tuple.
+/// Strings that are not valid UTF-8 are stored as empty strings.
+pub(crate) unsafe extern "C" fn diagnostic_callback(
+    kind: crate::raw::TableGenDiagKind::Type,
+    filename: TableGenStringRef,
+    line: u32,
+    column: u32,
+    message: TableGenStringRef,
+    line_contents: TableGenStringRef,
+    data: *mut c_void,
+) {
+    let diagnostics = &mut *(data as *mut Vec<Diagnostic>);
+
+    diagnostics.push(Diagnostic {
+        kind: DiagKind::from_raw(kind),
+        filename: StringRef::from_raw(filename)
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        line,
+        column,
+        message: StringRef::from_raw(message)
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        line_contents: StringRef::from_raw(line_contents)
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+    });
+}
+
+/// Backs [`crate::TableGenParser::add_include_resolver`]: owns the user's
+/// closure plus every source string it has produced so far, keeping them
+/// alive for as long as the parser that owns this state.
+pub(crate) struct IncludeResolverState {
+    pub(crate) resolver: Box<dyn FnMut(&str) -> Option<String>>,
+    pub(crate) resolved: Vec<CString>,
+}
+
+/// Installed as TableGen's include-file lookup hook. Tries the Rust closure
+/// in `state_data` before TableGen falls back to its on-disk search; on a
+/// hit, writes the resolved contents through `out_contents` (borrowed from a
+/// [`CString`] kept alive in `state_data` for the life of the parser).
+pub(crate) unsafe extern "C" fn include_resolver_callback(
+    path: TableGenStringRef,
+    state_data: *mut c_void,
+    out_contents: *mut TableGenStringRef,
+) -> i32 {
+    let state = &mut *(state_data as *mut IncludeResolverState);
+
+    let Ok(path) = StringRef::from_raw(path).as_str() else {
+        return 0;
+    };
+    let Some(contents) = (state.resolver)(path) else {
+        return 0;
+    };
+    let Ok(contents) = CString::new(contents) else {
+        return 0;
+    };
+
+    state.resolved.push(contents);
+    let stored = state.resolved.last().expect("just pushed");
+    *out_contents = TableGenStringRef {
+        data: stored.as_ptr() as *const _,
+        len: stored.as_bytes().len(),
+    };
+    1
+}
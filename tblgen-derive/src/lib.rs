@@ -0,0 +1,264 @@
+// Original work Copyright 2016 Alexander Stocko <as@coder.gg>.
+// Modified work Copyright 2023 Daan Vanoverloop
+// See the COPYRIGHT file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Companion proc-macro crate for `tblgen`.
+//!
+//! Provides `#[derive(FromRecord)]`, which generates a `from_record`
+//! constructor dispatching each field to the matching typed getter on
+//! [`tblgen::record::Record`](https://docs.rs/tblgen/latest/tblgen/record/struct.Record.html).
+//!
+//! ```ignore
+//! #[derive(FromRecord)]
+//! struct Instr {
+//!     size: i64,
+//!     name: String,
+//!     operands: Vec<Operand>,
+//!     #[tblgen(rename = "Predicate")]
+//!     predicate: Option<Reg>,
+//! }
+//! ```
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Fields, GenericArgument, PathArguments, Type,
+};
+
+/// Derives `FromRecord` for a struct, generating an `impl` that builds the
+/// struct from a `tblgen::record::Record` by dispatching on each field's
+/// Rust type to the matching typed getter.
+#[proc_macro_derive(FromRecord, attributes(tblgen))]
+pub fn derive_from_record(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_record_impl(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn from_record_impl(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input,
+            "FromRecord can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &data.fields,
+            "FromRecord requires named fields",
+        ));
+    };
+
+    let mut field_lets = Vec::new();
+    let mut field_names = Vec::new();
+    for field in &fields.named {
+        let ident = field.ident.as_ref().expect("named field");
+        let tblgen_name = rename_of(field)?.unwrap_or_else(|| ident.to_string());
+        field_lets.push(field_let(ident, &tblgen_name, &field.ty));
+        field_names.push(ident.clone());
+    }
+
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Builds `Self` from a `tblgen::record::Record`, aggregating
+            /// every field conversion failure into one `SourceError` instead
+            /// of stopping at the first.
+            pub fn from_record(
+                record: ::tblgen::record::Record,
+            ) -> Result<Self, ::tblgen::error::SourceError<::tblgen::error::TableGenError>> {
+                let mut __tblgen_errors: ::std::vec::Vec<(
+                    &'static str,
+                    ::tblgen::error::SourceError<::tblgen::error::TableGenError>,
+                )> = ::std::vec::Vec::new();
+                #(#field_lets)*
+                if __tblgen_errors.is_empty() {
+                    Ok(Self {
+                        #(#field_names: #field_names.unwrap()),*
+                    })
+                } else {
+                    let location = __tblgen_errors[0].1.location().clone();
+                    let message = __tblgen_errors
+                        .iter()
+                        .map(|(field, e)| format!("field `{}`: {}", field, e))
+                        .collect::<::std::vec::Vec<_>>()
+                        .join("; ");
+                    Err(::tblgen::error::SourceError::new(
+                        location,
+                        ::tblgen::error::TableGenError::Multiple(message),
+                    ))
+                }
+            }
+        }
+    })
+}
+
+fn rename_of(field: &syn::Field) -> syn::Result<Option<String>> {
+    for attr in &field.attrs {
+        if !attr.path().is_ident("tblgen") {
+            continue;
+        }
+        let mut rename = None;
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                let value = meta.value()?;
+                let lit: syn::LitStr = value.parse()?;
+                rename = Some(lit.value());
+                Ok(())
+            } else {
+                Err(meta.error("unsupported #[tblgen(..)] attribute"))
+            }
+        })?;
+        return Ok(rename);
+    }
+    Ok(None)
+}
+
+/// Generates the `let` binding that extracts one field's value from
+/// `record`, dispatching on the field's declared Rust type. On success binds
+/// `Some(value)`; on failure pushes the error onto `__tblgen_errors` and
+/// binds `None`, so every field is attempted before `from_record` gives up.
+fn field_let(ident: &syn::Ident, tblgen_name: &str, ty: &Type) -> TokenStream2 {
+    if let Some(inner) = option_inner(ty) {
+        let convert = option_convert(inner);
+        quote! {
+            let #ident = match record.value(#tblgen_name) {
+                Err(e) if matches!(
+                    e.error(),
+                    ::tblgen::error::TableGenError::MissingValue(_)
+                ) => Some(None),
+                Err(e) => {
+                    __tblgen_errors.push((#tblgen_name, e));
+                    None
+                }
+                Ok(value) => match #convert {
+                    Ok(v) => Some(v),
+                    Err(e) => {
+                        __tblgen_errors.push((#tblgen_name, e));
+                        None
+                    }
+                },
+            };
+        }
+    } else if let Some(inner) = vec_inner(ty) {
+        let list_getter = list_getter(inner, tblgen_name);
+        quote! {
+            let #ident = match #list_getter {
+                Ok(v) => Some(v),
+                Err(e) => {
+                    __tblgen_errors.push((#tblgen_name, e));
+                    None
+                }
+            };
+        }
+    } else {
+        let getter = scalar_getter(ty, tblgen_name);
+        quote! {
+            let #ident = match #getter {
+                Ok(v) => Some(v),
+                Err(e) => {
+                    __tblgen_errors.push((#tblgen_name, e));
+                    None
+                }
+            };
+        }
+    }
+}
+
+fn scalar_getter(ty: &Type, tblgen_name: &str) -> TokenStream2 {
+    if is_type(ty, "i64") {
+        quote! { record.int_value(#tblgen_name) }
+    } else if is_type(ty, "bool") {
+        quote! { record.bit_value(#tblgen_name) }
+    } else if is_type(ty, "String") {
+        quote! { record.string_value(#tblgen_name) }
+    } else {
+        quote! {
+            record
+                .def_value(#tblgen_name)
+                .and_then(<#ty>::from_record)
+        }
+    }
+}
+
+/// Generates the expression that converts an already-fetched `value`
+/// ([`tblgen::record::RecordValue`](https://docs.rs/tblgen/latest/tblgen/record/struct.RecordValue.html))
+/// into `Result<Option<#inner>, _>`, treating a declared-but-unset (`?`)
+/// field the same as an absent one instead of propagating the
+/// `InitConversion` error `Unset` produces against a typed getter.
+///
+/// For a primitive `inner`, this goes through `tblgen`'s blanket
+/// `Option<T>: TryFrom<TypedInit>`, which already maps `Unset` to `None`.
+/// Derived struct fields don't implement `TryFrom<TypedInit>` (they go
+/// through `from_record`), so those check for `Unset` by hand.
+fn option_convert(inner: &Type) -> TokenStream2 {
+    if is_type(inner, "i64") || is_type(inner, "bool") || is_type(inner, "String") {
+        quote! {
+            <::std::option::Option<#inner> as ::std::convert::TryFrom<_>>::try_from(value.init)
+        }
+    } else {
+        quote! {
+            match value.init {
+                ::tblgen::init::TypedInit::Unset => Ok(None),
+                init => ::tblgen::record::Record::try_from(init)
+                    .and_then(<#inner>::from_record)
+                    .map(Some),
+            }
+        }
+    }
+}
+
+fn list_getter(inner: &Type, tblgen_name: &str) -> TokenStream2 {
+    if is_type(inner, "i64") || is_type(inner, "bool") || is_type(inner, "String") {
+        quote! {
+            record.list_value(#tblgen_name).and_then(|list| {
+                list.iter().map(TryInto::try_into).collect::<Result<Vec<_>, _>>()
+            })
+        }
+    } else {
+        quote! {
+            record.list_value(#tblgen_name).and_then(|list| {
+                list.iter()
+                    .map(|init| ::tblgen::record::Record::try_from(init).and_then(<#inner>::from_record))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+        }
+    }
+}
+
+fn is_type(ty: &Type, name: &str) -> bool {
+    matches!(ty, Type::Path(p) if p.path.is_ident(name))
+}
+
+fn option_inner(ty: &Type) -> Option<&Type> {
+    generic_inner(ty, "Option")
+}
+
+fn vec_inner(ty: &Type) -> Option<&Type> {
+    generic_inner(ty, "Vec")
+}
+
+fn generic_inner<'t>(ty: &'t Type, wrapper: &str) -> Option<&'t Type> {
+    let Type::Path(p) = ty else { return None };
+    let segment = p.path.segments.last()?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    args.args.iter().find_map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    })
+}
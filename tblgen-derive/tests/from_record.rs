@@ -0,0 +1,143 @@
+// Original work Copyright 2016 Alexander Stocko <as@coder.gg>.
+// Modified work Copyright 2023 Daan Vanoverloop
+// See the COPYRIGHT file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use tblgen::TableGenParser;
+use tblgen_derive::FromRecord;
+
+#[derive(FromRecord, Debug, PartialEq)]
+struct Operand {
+    name: String,
+}
+
+#[derive(FromRecord, Debug, PartialEq)]
+struct Instruction {
+    size: i64,
+    mnemonic: String,
+    ops: Vec<Operand>,
+    predicate: Option<i64>,
+}
+
+#[test]
+fn derives_required_and_list_fields() {
+    let rk = TableGenParser::new()
+        .add_source(
+            "
+            class Operand {
+                string name;
+            }
+            def Op1 : Operand {
+                let name = \"src\";
+            }
+            def Op2 : Operand {
+                let name = \"dst\";
+            }
+            def Add {
+                int size = 4;
+                string mnemonic = \"add\";
+                list<Operand> ops = [Op1, Op2];
+                int predicate = 1;
+            }
+            ",
+        )
+        .unwrap()
+        .parse()
+        .expect("valid tablegen");
+    let add = rk.def("Add").expect("def Add exists");
+
+    let instr = Instruction::from_record(add).expect("converts cleanly");
+    assert_eq!(
+        instr,
+        Instruction {
+            size: 4,
+            mnemonic: "add".to_string(),
+            ops: vec![
+                Operand { name: "src".to_string() },
+                Operand { name: "dst".to_string() },
+            ],
+            predicate: Some(1),
+        }
+    );
+}
+
+#[derive(FromRecord, Debug, PartialEq)]
+struct Simple {
+    size: i64,
+    mnemonic: String,
+    ops: Vec<i64>,
+    predicate: Option<i64>,
+}
+
+#[test]
+fn unset_option_field_converts_to_none() {
+    let rk = TableGenParser::new()
+        .add_source(
+            "
+            def Add {
+                int size = 4;
+                string mnemonic = \"add\";
+                list<int> ops = [];
+                int predicate = ?;
+            }
+            ",
+        )
+        .unwrap()
+        .parse()
+        .expect("valid tablegen");
+    let add = rk.def("Add").expect("def Add exists");
+
+    let instr = Simple::from_record(add).expect("converts cleanly");
+    assert_eq!(instr.predicate, None);
+}
+
+#[test]
+fn absent_option_field_converts_to_none() {
+    // `predicate` isn't declared anywhere in `Add`'s class hierarchy, so
+    // `record.value("predicate")` fails with `MissingValue` rather than
+    // yielding an `Unset` init -- a different failure mode than the `?`
+    // case above, and one `FromRecord` must forgive the same way.
+    let rk = TableGenParser::new()
+        .add_source(
+            "
+            def Add {
+                int size = 4;
+                string mnemonic = \"add\";
+                list<int> ops = [];
+            }
+            ",
+        )
+        .unwrap()
+        .parse()
+        .expect("valid tablegen");
+    let add = rk.def("Add").expect("def Add exists");
+
+    let instr = Simple::from_record(add).expect("converts cleanly");
+    assert_eq!(instr.predicate, None);
+}
+
+#[test]
+fn multiple_missing_required_fields_are_all_reported() {
+    let rk = TableGenParser::new()
+        .add_source(
+            "
+            def Bad {
+            }
+            ",
+        )
+        .unwrap()
+        .parse()
+        .expect("valid tablegen");
+    let bad = rk.def("Bad").expect("def Bad exists");
+
+    let err = Simple::from_record(bad).expect_err("size, mnemonic and ops are all missing");
+    let message = err.to_string();
+    assert!(message.contains("size"), "message was: {message}");
+    assert!(message.contains("mnemonic"), "message was: {message}");
+    assert!(message.contains("ops"), "message was: {message}");
+}